@@ -0,0 +1,90 @@
+use hound::SampleFormat;
+
+/// Decodes raw little-endian sample bytes of the given `width`/`format` into normalized
+/// `f32` samples in `[-1.0, 1.0]`, dividing integer samples by their format's max magnitude.
+///
+/// 8-bit PCM is the one WAV/FSB integer width stored unsigned rather than two's-complement,
+/// so it's biased by 128 instead of sign-extended.
+pub fn normalize(data: &[u8], width: u16, format: SampleFormat) -> Vec<f32> {
+    match format {
+        SampleFormat::Float => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        SampleFormat::Int if width == 1 => data
+            .iter()
+            .map(|&b| (i32::from(b) - 128) as f32 / 128.0)
+            .collect(),
+        SampleFormat::Int => {
+            let max = (1i64 << (width * 8 - 1)) as f32;
+            data.chunks_exact(width as usize)
+                .map(|b| sign_extend(b) as f32 / max)
+                .collect()
+        }
+    }
+}
+
+/// Re-quantizes normalized `f32` samples back to raw little-endian bytes of the given
+/// `width`/`format`, scaling and clamping integer samples to their target bit depth.
+///
+/// 8-bit PCM is the one WAV/FSB integer width stored unsigned rather than two's-complement,
+/// so it's biased by 128 instead of clamped to a symmetric signed range.
+pub fn requantize(samples: &[f32], width: u16, format: SampleFormat) -> Vec<u8> {
+    match format {
+        SampleFormat::Float => samples.iter().flat_map(|s| s.to_le_bytes()).collect(),
+        SampleFormat::Int if width == 1 => samples
+            .iter()
+            .map(|s| ((s * 128.0).round().clamp(-128.0, 127.0) as i32 + 128) as u8)
+            .collect(),
+        SampleFormat::Int => {
+            let max = (1i64 << (width * 8 - 1)) as f32 - 1.0;
+            samples
+                .iter()
+                .flat_map(|s| {
+                    let quantized = (s * max).round().clamp(-max - 1.0, max) as i32;
+                    quantized.to_le_bytes()[..width as usize].to_vec()
+                })
+                .collect()
+        }
+    }
+}
+
+/// Sign-extends a little-endian integer sample narrower than 32 bits.
+fn sign_extend(bytes: &[u8]) -> i32 {
+    let mut buf = [0; 4];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    let shift = (4 - bytes.len()) * 8;
+    (i32::from_le_bytes(buf) << shift) >> shift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_treats_8_bit_pcm_as_unsigned() {
+        assert_eq!(normalize(&[0], 1, SampleFormat::Int), vec![-1.0]);
+        assert_eq!(normalize(&[128], 1, SampleFormat::Int), vec![0.0]);
+        assert_eq!(normalize(&[255], 1, SampleFormat::Int), vec![127.0 / 128.0]);
+    }
+
+    #[test]
+    fn requantize_treats_8_bit_pcm_as_unsigned() {
+        assert_eq!(requantize(&[-1.0], 1, SampleFormat::Int), vec![0]);
+        assert_eq!(requantize(&[0.0], 1, SampleFormat::Int), vec![128]);
+        assert_eq!(requantize(&[1.0], 1, SampleFormat::Int), vec![255]);
+    }
+
+    #[test]
+    fn normalize_sign_extends_16_bit_pcm() {
+        let data = i16::MIN.to_le_bytes();
+        assert_eq!(normalize(&data, 2, SampleFormat::Int), vec![-1.0]);
+    }
+
+    #[test]
+    fn requantize_round_trips_through_normalize() {
+        let data = [0x34, 0x12];
+        let normalized = normalize(&data, 2, SampleFormat::Int);
+        assert_eq!(requantize(&normalized, 2, SampleFormat::Int), data);
+    }
+}
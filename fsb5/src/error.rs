@@ -19,12 +19,38 @@ pub enum Error {
     #[error("Frequency value `{0}` is not valid and no frequency metadata chunk was provided")]
     Frequency(u32),
 
+    #[error("Channel count `{0}` is not valid, expected at least `1`")]
+    Channels(u64),
+
     #[error("Non UTF-8 content in name table for sample `{0}`")]
     NameTable(usize),
 
+    #[error("No sample at index `{0}`")]
+    SampleIndex(usize),
+
+    #[error("Sample data has not been loaded yet, call `load_sample` first")]
+    SampleNotLoaded,
+
+    #[error("Channel `{channel}` only decoded `{got}` of the expected `{expected}` ADPCM samples")]
+    AdpcmUnderrun {
+        channel: usize,
+        got: usize,
+        expected: usize,
+    },
+
+    #[error("DSPCOEFF chunk is `{got}` bytes, too short for `{channels}` channel(s) (`{expected}` needed)")]
+    AdpcmCoefficients {
+        channels: usize,
+        expected: usize,
+        got: usize,
+    },
+
     #[error("Sample to decode did not originate from the FSB archive decoding it")]
     Mismatched,
 
+    #[error("Sample is missing the metadata chunk `{0}` required to decode it")]
+    MissingMetadataChunk(u64),
+
     #[error("Decoding samples of type `{0:?}` is not supported")]
     RebuildFormat(SoundFormat),
 
@@ -0,0 +1,220 @@
+use crate::{Error, MetadataChunk, Sample};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::Cursor;
+
+const COEFFICIENTS_PER_CHANNEL: usize = 16;
+const FRAME_SIZE: usize = 8;
+
+fn read_coefficients(data: &[u8]) -> Result<[[i32; 2]; 8], Error> {
+    let mut reader = Cursor::new(data);
+    let mut pairs = [[0; 2]; 8];
+    for pair in pairs.iter_mut() {
+        pair[0] = i32::from(reader.read_i16::<BigEndian>()?);
+        pair[1] = i32::from(reader.read_i16::<BigEndian>()?);
+    }
+    Ok(pairs)
+}
+
+fn decode_channel(data: &[u8], coefficients: &[[i32; 2]; 8], samples: usize) -> Vec<i16> {
+    let mut out = Vec::with_capacity(samples);
+    let mut hist1 = 0i32;
+    let mut hist2 = 0i32;
+
+    'frames: for frame in data.chunks(FRAME_SIZE) {
+        let header = frame[0];
+        let scale = 1i32 << (header & 0x0F);
+        let [c1, c2] = coefficients[(header >> 4) as usize];
+
+        for &byte in &frame[1..] {
+            for nibble in [byte >> 4, byte & 0x0F] {
+                if out.len() >= samples {
+                    break 'frames;
+                }
+
+                let nibble = if nibble >= 8 {
+                    i32::from(nibble) - 16
+                } else {
+                    i32::from(nibble)
+                };
+                let predicted = (((nibble * scale) << 11) + 1024 + c1 * hist1 + c2 * hist2) >> 11;
+                let sample = predicted.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16;
+
+                hist2 = hist1;
+                hist1 = i32::from(sample);
+                out.push(sample);
+            }
+        }
+    }
+
+    out
+}
+
+pub fn rebuild(sample: Sample) -> Result<Vec<u8>, Error> {
+    let channels = sample.channels as usize;
+    if channels == 0 {
+        return Err(Error::Channels(sample.channels));
+    }
+
+    let coefficients = match sample.metadata.get(&7) {
+        Some(MetadataChunk::DSPCOEFF(data)) => data,
+        _ => return Err(Error::MissingMetadataChunk(7)),
+    };
+    let expected_coefficients_len = channels * COEFFICIENTS_PER_CHANNEL * 2;
+    if coefficients.len() < expected_coefficients_len {
+        return Err(Error::AdpcmCoefficients {
+            channels,
+            expected: expected_coefficients_len,
+            got: coefficients.len(),
+        });
+    }
+
+    let data = sample.data.ok_or(Error::SampleNotLoaded)?;
+    let channel_data_len = data.len() / channels;
+    let mut channel_samples = Vec::with_capacity(channels);
+    for (channel, channel_data) in data.chunks(channel_data_len).enumerate() {
+        let offset = channel * COEFFICIENTS_PER_CHANNEL * 2;
+        let coefficients = read_coefficients(&coefficients[offset..offset + COEFFICIENTS_PER_CHANNEL * 2])?;
+        let decoded = decode_channel(channel_data, &coefficients, sample.samples);
+        if decoded.len() != sample.samples {
+            return Err(Error::AdpcmUnderrun {
+                channel,
+                got: decoded.len(),
+                expected: sample.samples,
+            });
+        }
+        channel_samples.push(decoded);
+    }
+
+    let mut interleaved = Vec::with_capacity(sample.samples * channels * 2);
+    for i in 0..sample.samples {
+        for channel in &channel_samples {
+            interleaved.extend_from_slice(&channel[i].to_le_bytes());
+        }
+    }
+
+    write_wav(&interleaved, sample.channels as u16, sample.frequency)
+}
+
+/// Wraps decoded 16-bit PCM in a minimal canonical WAV container (`fmt `/`data` chunks
+/// only).
+fn write_wav(data: &[u8], channels: u16, sample_rate: u32) -> Result<Vec<u8>, Error> {
+    let block_align = channels * 2;
+    let byte_rate = sample_rate * u32::from(block_align);
+
+    let mut out = Vec::with_capacity(44 + data.len());
+    out.extend_from_slice(b"RIFF");
+    out.write_u32::<LittleEndian>(36 + data.len() as u32)?;
+    out.extend_from_slice(b"WAVE");
+
+    out.extend_from_slice(b"fmt ");
+    out.write_u32::<LittleEndian>(16)?;
+    out.write_u16::<LittleEndian>(1)?; // format tag: PCM
+    out.write_u16::<LittleEndian>(channels)?;
+    out.write_u32::<LittleEndian>(sample_rate)?;
+    out.write_u32::<LittleEndian>(byte_rate)?;
+    out.write_u16::<LittleEndian>(block_align)?;
+    out.write_u16::<LittleEndian>(16)?; // bits per sample
+
+    out.extend_from_slice(b"data");
+    out.write_u32::<LittleEndian>(data.len() as u32)?;
+    out.extend_from_slice(data);
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn decode_channel_applies_adpcm_predictor() {
+        let coefficients = [[0, 0]; 8];
+        // header selects coefficient pair 0 (c1 = c2 = 0) and scale 1 << 0 = 1; the two
+        // nibbles of the following byte are 1 and 0.
+        let frame = [0x00, 0x10, 0, 0, 0, 0, 0, 0];
+
+        let decoded = decode_channel(&frame, &coefficients, 2);
+
+        assert_eq!(decoded, vec![1, 0]);
+    }
+
+    #[test]
+    fn rebuild_errors_on_adpcm_underrun_instead_of_panicking() {
+        let mut metadata = HashMap::new();
+        metadata.insert(7, MetadataChunk::DSPCOEFF(vec![0; COEFFICIENTS_PER_CHANNEL * 2]));
+
+        let sample = Sample {
+            name: "test".to_string(),
+            frequency: 44100,
+            channels: 1,
+            data_offset: 0,
+            samples: 100,
+            metadata,
+            // A single 8-byte frame only decodes to 14 samples, well short of the 100
+            // the sample header claims.
+            data: Some(vec![0; FRAME_SIZE]),
+        };
+
+        let err = rebuild(sample).unwrap_err();
+        assert!(matches!(err, Error::AdpcmUnderrun { .. }));
+    }
+
+    #[test]
+    fn rebuild_errors_on_zero_channels_instead_of_panicking() {
+        let mut metadata = HashMap::new();
+        metadata.insert(7, MetadataChunk::DSPCOEFF(vec![0; COEFFICIENTS_PER_CHANNEL * 2]));
+
+        let sample = Sample {
+            name: "test".to_string(),
+            frequency: 44100,
+            channels: 0,
+            data_offset: 0,
+            samples: 0,
+            metadata,
+            data: Some(Vec::new()),
+        };
+
+        let err = rebuild(sample).unwrap_err();
+        assert!(matches!(err, Error::Channels(0)));
+    }
+
+    #[test]
+    fn rebuild_errors_on_undersized_coefficients_instead_of_panicking() {
+        let mut metadata = HashMap::new();
+        // Only one channel's worth of coefficients for a sample claiming 2 channels.
+        metadata.insert(7, MetadataChunk::DSPCOEFF(vec![0; COEFFICIENTS_PER_CHANNEL * 2]));
+
+        let sample = Sample {
+            name: "test".to_string(),
+            frequency: 44100,
+            channels: 2,
+            data_offset: 0,
+            samples: 0,
+            metadata,
+            data: Some(vec![0; FRAME_SIZE * 2]),
+        };
+
+        let err = rebuild(sample).unwrap_err();
+        assert!(matches!(err, Error::AdpcmCoefficients { channels: 2, .. }));
+    }
+
+    #[test]
+    fn rebuild_errors_instead_of_panicking_when_data_was_not_loaded() {
+        let mut metadata = HashMap::new();
+        metadata.insert(7, MetadataChunk::DSPCOEFF(vec![0; COEFFICIENTS_PER_CHANNEL * 2]));
+
+        let sample = Sample {
+            name: "test".to_string(),
+            frequency: 44100,
+            channels: 1,
+            data_offset: 0,
+            samples: 0,
+            metadata,
+            data: None,
+        };
+
+        let err = rebuild(sample).unwrap_err();
+        assert!(matches!(err, Error::SampleNotLoaded));
+    }
+}
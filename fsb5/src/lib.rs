@@ -8,9 +8,35 @@ use std::{
 mod error;
 pub use error::Error;
 
+#[cfg(feature = "pcm")]
+mod convert;
+
 #[cfg(feature = "pcm")]
 mod pcm;
 
+#[cfg(feature = "pcm")]
+pub mod remix;
+
+#[cfg(feature = "gcadpcm")]
+mod gcadpcm;
+
+#[cfg(feature = "vorbis")]
+mod vorbis;
+
+/// Maps a PCM [`SoundFormat`] to the `(width, hound::SampleFormat)` pair [`pcm`] writes it
+/// with, or `None` for non-PCM formats.
+#[cfg(feature = "pcm")]
+fn pcm_layout(format: SoundFormat) -> Option<(u16, hound::SampleFormat)> {
+    match format {
+        SoundFormat::PCM8 => Some((1, hound::SampleFormat::Int)),
+        SoundFormat::PCM16 => Some((2, hound::SampleFormat::Int)),
+        SoundFormat::PCM24 => Some((3, hound::SampleFormat::Int)),
+        SoundFormat::PCM32 => Some((4, hound::SampleFormat::Int)),
+        SoundFormat::PCMFloat => Some((4, hound::SampleFormat::Float)),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum SoundFormat {
     None,
@@ -36,7 +62,11 @@ impl SoundFormat {
         match self {
             SoundFormat::MPEG => "mp3",
             SoundFormat::Vorbis => "ogg",
-            SoundFormat::PCM8 | SoundFormat::PCM16 | SoundFormat::PCM32 => "wav",
+            SoundFormat::PCM8
+            | SoundFormat::PCM16
+            | SoundFormat::PCM24
+            | SoundFormat::PCM32
+            | SoundFormat::PCMFloat => "wav",
             _ => "bin",
         }
     }
@@ -212,7 +242,12 @@ pub struct FSB5 {
 }
 
 impl FSB5 {
-    pub fn read<R: BufRead + Seek>(mut reader: R) -> Result<Self, Error> {
+    /// Parses the FSB5 header, per-sample metadata and name table without reading any
+    /// sample data, leaving every [`Sample::data`] as `None`. Use
+    /// [`load_sample`](Self::load_sample) to read a given sample's bytes from `reader` on
+    /// demand afterwards, which avoids buffering the whole data section for banks the
+    /// caller only needs a few samples (or just the headers) from.
+    pub fn read_streamed<R: BufRead + Seek>(mut reader: R) -> Result<Self, Error> {
         let mut magic = [0; 4];
         reader.read_exact(&mut magic)?;
         if magic != *b"FSB5" {
@@ -230,7 +265,7 @@ impl FSB5 {
             let mut raw = reader.read_u64::<LittleEndian>()?;
             let mut next_chunk = bits(raw, 0, 1);
             let mut frequency = bits(raw, 1, 4) as u32;
-            let channels = bits(raw, 1 + 4, 1) + 1;
+            let mut channels = bits(raw, 1 + 4, 1) + 1;
             let data_offset = (bits(raw, 1 + 4 + 1, 28) * 16) as usize;
             let self_samples = bits(raw, 1 + 4 + 1 + 28, 30) as usize;
 
@@ -255,6 +290,13 @@ impl FSB5 {
                 chunks.insert(chunk_type, chunk_data);
             }
 
+            if let Some(MetadataChunk::Channels(c)) = chunks.get(&1) {
+                if *c == 0 {
+                    return Err(Error::Channels(u64::from(*c)));
+                }
+                channels = u64::from(*c);
+            }
+
             if let Some(MetadataChunk::Frequency(f)) = chunks.get(&2) {
                 frequency = *f;
             } else {
@@ -303,21 +345,6 @@ impl FSB5 {
             }
         }
 
-        reader.seek(SeekFrom::Start(
-            (header.size + header.sample_headers_size + header.name_table_size) as u64,
-        ))?;
-        for i in 0..header.num_samples {
-            let data_start = samples.get(i).unwrap().data_offset;
-            let data_end = if i < header.num_samples - 1 {
-                samples.get(i + 1).unwrap().data_offset
-            } else {
-                data_start + header.data_size
-            };
-            let mut data = Vec::with_capacity(data_end - data_start);
-            reader.read_exact(&mut data)?;
-            samples.get_mut(i).unwrap().data = Some(data);
-        }
-
         Ok(Self {
             header,
             raw_size,
@@ -325,16 +352,91 @@ impl FSB5 {
         })
     }
 
+    /// Convenience wrapper over [`read_streamed`](Self::read_streamed) that also eagerly
+    /// loads every sample's data via [`load_sample`](Self::load_sample).
+    pub fn read<R: BufRead + Seek>(mut reader: R) -> Result<Self, Error> {
+        let mut fsb = Self::read_streamed(&mut reader)?;
+        for i in 0..fsb.samples.len() {
+            fsb.samples[i].data = fsb.load_sample(i, &mut reader)?.data;
+        }
+        Ok(fsb)
+    }
+
+    /// Seeks `reader` to the `index`th sample's data and reads exactly its bytes, without
+    /// touching any other sample. `reader` must be positioned over the same underlying FSB5
+    /// data this `FSB5` was parsed from.
+    pub fn load_sample<R: Read + Seek>(&self, index: usize, reader: &mut R) -> Result<Sample, Error> {
+        let sample = self.samples.get(index).ok_or(Error::SampleIndex(index))?;
+
+        let data_start =
+            self.header.size + self.header.sample_headers_size + self.header.name_table_size;
+        let offset = data_start + sample.data_offset;
+        let next_offset = match self.samples.get(index + 1) {
+            Some(next) => data_start + next.data_offset,
+            None => data_start + self.header.data_size,
+        };
+
+        reader.seek(SeekFrom::Start(offset as u64))?;
+        let mut data = vec![0; next_offset - offset];
+        reader.read_exact(&mut data)?;
+
+        Ok(Sample {
+            data: Some(data),
+            ..sample.clone()
+        })
+    }
+
     pub fn rebuild(&self, sample: Sample) -> Result<Vec<u8>, Error> {
+        self.rebuild_as(sample, self.header.mode)
+    }
+
+    /// Like [`rebuild`](Self::rebuild), but requests the sample be produced as `target`
+    /// instead of the FSB's native `mode`. Only meaningful between PCM formats, where the
+    /// samples are transcoded via the `convert` module; any other `target` falls back to
+    /// decoding the sample in its native format.
+    pub fn rebuild_as(&self, sample: Sample, target: SoundFormat) -> Result<Vec<u8>, Error> {
+        #[cfg(feature = "pcm")]
+        {
+            if let (Some((dst_width, dst_format)), Some((src_width, src_format))) =
+                (pcm_layout(target), pcm_layout(self.header.mode))
+            {
+                return if (dst_width, dst_format) == (src_width, src_format) {
+                    pcm::rebuild(sample, dst_width, dst_format)
+                } else {
+                    pcm::rebuild_converted(sample, src_width, src_format, dst_width, dst_format)
+                };
+            }
+        }
+
         match self.header.mode {
-            SoundFormat::MPEG => Ok(sample.data.unwrap()),
+            SoundFormat::MPEG => sample.data.ok_or(Error::SampleNotLoaded),
             #[cfg(feature = "pcm")]
-            SoundFormat::PCM8 => pcm::rebuild(sample, 1),
+            SoundFormat::PCM8 => pcm::rebuild(sample, 1, hound::SampleFormat::Int),
             #[cfg(feature = "pcm")]
-            SoundFormat::PCM16 => pcm::rebuild(sample, 2),
+            SoundFormat::PCM16 => pcm::rebuild(sample, 2, hound::SampleFormat::Int),
             #[cfg(feature = "pcm")]
-            SoundFormat::PCM32 => pcm::rebuild(sample, 4),
+            SoundFormat::PCM24 => pcm::rebuild(sample, 3, hound::SampleFormat::Int),
+            #[cfg(feature = "pcm")]
+            SoundFormat::PCM32 => pcm::rebuild(sample, 4, hound::SampleFormat::Int),
+            #[cfg(feature = "pcm")]
+            SoundFormat::PCMFloat => pcm::rebuild(sample, 4, hound::SampleFormat::Float),
+            #[cfg(feature = "gcadpcm")]
+            SoundFormat::GCADPCM => gcadpcm::rebuild(sample),
+            #[cfg(feature = "vorbis")]
+            SoundFormat::Vorbis => vorbis::rebuild(sample),
             _ => Err(Error::RebuildFormat(self.header.mode)),
         }
     }
+
+    /// Like [`rebuild`](Self::rebuild), but applies `op` to the sample's deinterleaved
+    /// channels before writing it out, letting a multi-channel sample be remapped or
+    /// downmixed. Only meaningful for PCM formats, where the samples can be deinterleaved,
+    /// remixed and requantized losslessly; any other native format returns
+    /// [`Error::RebuildFormat`].
+    #[cfg(feature = "pcm")]
+    pub fn rebuild_remixed(&self, sample: Sample, op: &remix::ChannelOp) -> Result<Vec<u8>, Error> {
+        let (width, format) =
+            pcm_layout(self.header.mode).ok_or(Error::RebuildFormat(self.header.mode))?;
+        pcm::rebuild_remixed(sample, width, format, op)
+    }
 }
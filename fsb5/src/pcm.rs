@@ -1,26 +1,168 @@
-use crate::{Error, Sample};
-use hound::{ChunksWriter, SampleFormat, WavSpec};
-use std::io::{BufWriter, Cursor, Write};
-
-pub fn rebuild(sample: Sample, width: u16) -> Result<Vec<u8>, Error> {
-    let data = &sample.data.unwrap()[..(sample.samples * width as usize)];
-    let mut writer = BufWriter::new(Cursor::new(Vec::new()));
-
-    let spec = WavSpec {
-        channels: sample.channels as u16,
-        sample_rate: sample.frequency,
-        bits_per_sample: width,
-        sample_format: SampleFormat::Int,
+use crate::remix::{self, ChannelOp};
+use crate::{convert, Error, Sample};
+use byteorder::{LittleEndian, WriteBytesExt};
+use hound::SampleFormat;
+
+pub fn rebuild(sample: Sample, width: u16, format: SampleFormat) -> Result<Vec<u8>, Error> {
+    let sample_size = match format {
+        SampleFormat::Int => width as usize,
+        SampleFormat::Float => 4,
     };
-    {
-        let mut chunk_writer = ChunksWriter::new(&mut writer)?;
-        chunk_writer.write_fmt(spec)?;
-        {
-            let mut embedded_writer = chunk_writer.start_chunk(*b"data")?;
-            embedded_writer.write_all(data)?;
+    let data = sample.data.ok_or(Error::SampleNotLoaded)?;
+    let data = &data[..(sample.samples * sample_size)];
+    write_wav(data, sample.channels as u16, sample.frequency, width, format)
+}
+
+/// Like [`rebuild`], but transcodes the raw samples from `(src_width, src_format)` to
+/// `(dst_width, dst_format)` before writing them out.
+pub fn rebuild_converted(
+    sample: Sample,
+    src_width: u16,
+    src_format: SampleFormat,
+    dst_width: u16,
+    dst_format: SampleFormat,
+) -> Result<Vec<u8>, Error> {
+    let src_sample_size = match src_format {
+        SampleFormat::Int => src_width as usize,
+        SampleFormat::Float => 4,
+    };
+    let data = sample.data.ok_or(Error::SampleNotLoaded)?;
+    let data = &data[..(sample.samples * src_sample_size)];
+
+    let normalized = convert::normalize(data, src_width, src_format);
+    let converted = convert::requantize(&normalized, dst_width, dst_format);
+    write_wav(
+        &converted,
+        sample.channels as u16,
+        sample.frequency,
+        dst_width,
+        dst_format,
+    )
+}
+
+/// Like [`rebuild`], but applies `op` to the deinterleaved, normalized samples before
+/// writing them out, letting a caller remap or downmix the channel layout.
+pub fn rebuild_remixed(
+    sample: Sample,
+    width: u16,
+    format: SampleFormat,
+    op: &ChannelOp,
+) -> Result<Vec<u8>, Error> {
+    let sample_size = match format {
+        SampleFormat::Int => width as usize,
+        SampleFormat::Float => 4,
+    };
+    let channels = sample.channels as usize;
+    if channels == 0 {
+        return Err(Error::Channels(sample.channels));
+    }
+
+    let frequency = sample.frequency;
+    let data = sample.data.ok_or(Error::SampleNotLoaded)?;
+    let data = &data[..(sample.samples * channels * sample_size)];
+
+    let normalized = convert::normalize(data, width, format);
+    let remixed = op.apply(&remix::deinterleave(&normalized, channels));
+    let converted = convert::requantize(&remix::interleave(&remixed), width, format);
+
+    write_wav(&converted, remixed.len() as u16, frequency, width, format)
+}
+
+/// Wraps already-encoded PCM bytes in a minimal canonical WAV container (`fmt `/`data`
+/// chunks only), since `data` is already in its final on-disk layout and just needs a
+/// RIFF header around it.
+fn write_wav(
+    data: &[u8],
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u16,
+    sample_format: SampleFormat,
+) -> Result<Vec<u8>, Error> {
+    let format_tag: u16 = match sample_format {
+        SampleFormat::Int => 1,
+        SampleFormat::Float => 3,
+    };
+    let block_align = channels * (bits_per_sample / 8);
+    let byte_rate = sample_rate * u32::from(block_align);
+
+    let mut out = Vec::with_capacity(44 + data.len());
+    out.extend_from_slice(b"RIFF");
+    out.write_u32::<LittleEndian>(36 + data.len() as u32)?;
+    out.extend_from_slice(b"WAVE");
+
+    out.extend_from_slice(b"fmt ");
+    out.write_u32::<LittleEndian>(16)?;
+    out.write_u16::<LittleEndian>(format_tag)?;
+    out.write_u16::<LittleEndian>(channels)?;
+    out.write_u32::<LittleEndian>(sample_rate)?;
+    out.write_u32::<LittleEndian>(byte_rate)?;
+    out.write_u16::<LittleEndian>(block_align)?;
+    out.write_u16::<LittleEndian>(bits_per_sample)?;
+
+    out.extend_from_slice(b"data");
+    out.write_u32::<LittleEndian>(data.len() as u32)?;
+    out.extend_from_slice(data);
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn mono_sample(data: Vec<u8>, samples: usize) -> Sample {
+        Sample {
+            name: "test".to_string(),
+            frequency: 44100,
+            channels: 1,
+            data_offset: 0,
+            samples,
+            metadata: HashMap::new(),
+            data: Some(data),
         }
-        chunk_writer.finalize()?;
     }
 
-    Ok(writer.into_inner().unwrap().into_inner())
+    #[test]
+    fn rebuild_writes_a_canonical_wav_header() {
+        let wav = rebuild(mono_sample(vec![0x34, 0x12], 1), 2, SampleFormat::Int).unwrap();
+
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(&wav[44..], [0x34, 0x12]);
+    }
+
+    #[test]
+    fn rebuild_converted_widens_unsigned_8_bit_pcm_to_signed_16_bit() {
+        // 8-bit PCM is unsigned, so the silence point is 128, not 0.
+        let wav = rebuild_converted(
+            mono_sample(vec![128], 1),
+            1,
+            SampleFormat::Int,
+            2,
+            SampleFormat::Int,
+        )
+        .unwrap();
+
+        assert_eq!(&wav[44..], 0i16.to_le_bytes());
+    }
+
+    #[test]
+    fn rebuild_errors_instead_of_panicking_when_data_was_not_loaded() {
+        let mut sample = mono_sample(Vec::new(), 1);
+        sample.data = None;
+
+        let err = rebuild(sample, 2, SampleFormat::Int).unwrap_err();
+        assert!(matches!(err, Error::SampleNotLoaded));
+    }
+
+    #[test]
+    fn rebuild_remixed_errors_on_zero_channels_instead_of_panicking() {
+        let mut sample = mono_sample(vec![0, 0], 1);
+        sample.channels = 0;
+
+        let err = rebuild_remixed(sample, 2, SampleFormat::Int, &ChannelOp::Passthrough).unwrap_err();
+        assert!(matches!(err, Error::Channels(0)));
+    }
 }
@@ -0,0 +1,120 @@
+/// A channel operation applied to deinterleaved, normalized samples before they're handed
+/// back to the `pcm` writer. Operates on one `Vec<f32>` per input channel and produces one
+/// `Vec<f32>` per output channel.
+pub enum ChannelOp {
+    /// Leaves the channel layout untouched.
+    Passthrough,
+    /// Reorders and/or drops channels: output channel `i` is input channel `indices[i]`.
+    Reorder(Vec<usize>),
+    /// Broadcasts channel 0 to `n` identical output channels.
+    DupMono(usize),
+    /// Matrix downmix: output channel `i` is the weighted sum of input channels using
+    /// `matrix[i]` as coefficients.
+    Remix(Vec<Vec<f32>>),
+}
+
+impl ChannelOp {
+    /// The standard 5.1 to stereo downmix: front L/R pass through at unity gain, center
+    /// and surrounds fold in at `-3dB` (`1/sqrt(2)`), LFE is dropped. Expects channels in
+    /// FMOD's `L, R, C, LFE, Ls, Rs` order.
+    pub fn downmix_5_1_to_stereo() -> Self {
+        let side = std::f32::consts::FRAC_1_SQRT_2;
+        ChannelOp::Remix(vec![
+            vec![1.0, 0.0, side, 0.0, side, 0.0],
+            vec![0.0, 1.0, side, 0.0, 0.0, side],
+        ])
+    }
+
+    pub fn apply(&self, channels: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        match self {
+            ChannelOp::Passthrough => channels.to_vec(),
+            ChannelOp::Reorder(indices) => indices.iter().map(|&i| channels[i].clone()).collect(),
+            ChannelOp::DupMono(n) => vec![channels[0].clone(); *n],
+            ChannelOp::Remix(matrix) => {
+                let frames = channels[0].len();
+                matrix
+                    .iter()
+                    .map(|coefficients| {
+                        (0..frames)
+                            .map(|frame| {
+                                coefficients
+                                    .iter()
+                                    .zip(channels)
+                                    .map(|(coefficient, channel)| coefficient * channel[frame])
+                                    .sum()
+                            })
+                            .collect()
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Splits an interleaved sample stream into one `Vec<f32>` per channel.
+pub fn deinterleave(data: &[f32], channels: usize) -> Vec<Vec<f32>> {
+    let mut out = vec![Vec::with_capacity(data.len() / channels); channels];
+    for frame in data.chunks(channels) {
+        for (channel, &sample) in out.iter_mut().zip(frame) {
+            channel.push(sample);
+        }
+    }
+    out
+}
+
+/// Interleaves one `Vec<f32>` per channel back into a single sample stream.
+pub fn interleave(channels: &[Vec<f32>]) -> Vec<f32> {
+    let frames = channels[0].len();
+    let mut out = Vec::with_capacity(frames * channels.len());
+    for frame in 0..frames {
+        for channel in channels {
+            out.push(channel[frame]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deinterleave_then_interleave_round_trips() {
+        let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let channels = deinterleave(&data, 2);
+        assert_eq!(channels, vec![vec![1.0, 3.0, 5.0], vec![2.0, 4.0, 6.0]]);
+        assert_eq!(interleave(&channels), data);
+    }
+
+    #[test]
+    fn dup_mono_broadcasts_the_first_channel() {
+        let channels = vec![vec![1.0, 2.0], vec![9.0, 9.0]];
+        let out = ChannelOp::DupMono(3).apply(&channels);
+        assert_eq!(out, vec![vec![1.0, 2.0]; 3]);
+    }
+
+    #[test]
+    fn reorder_selects_channels_by_index() {
+        let channels = vec![vec![1.0], vec![2.0], vec![3.0]];
+        let out = ChannelOp::Reorder(vec![2, 0]).apply(&channels);
+        assert_eq!(out, vec![vec![3.0], vec![1.0]]);
+    }
+
+    #[test]
+    fn downmix_5_1_to_stereo_folds_center_and_surrounds_at_minus_3db() {
+        // L, R, C, LFE, Ls, Rs, one frame each, front L/R silent so only the folded-in
+        // channels show up in the result.
+        let channels = vec![
+            vec![0.0],
+            vec![0.0],
+            vec![1.0],
+            vec![1.0],
+            vec![1.0],
+            vec![1.0],
+        ];
+        let out = ChannelOp::downmix_5_1_to_stereo().apply(&channels);
+
+        let side = std::f32::consts::FRAC_1_SQRT_2;
+        assert_eq!(out, vec![vec![2.0 * side], vec![2.0 * side]]);
+    }
+}
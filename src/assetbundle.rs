@@ -1,9 +1,10 @@
+use crate::enums::CompressionType;
 use crate::Error;
 use byteorder::{BigEndian, ReadBytesExt};
 use std::convert::TryInto;
 use std::{
     convert::TryFrom,
-    io::{BufRead, Read, Seek, SeekFrom},
+    io::{BufRead, Cursor, Read, Seek, SeekFrom},
 };
 
 #[derive(Debug, Clone)]
@@ -48,6 +49,22 @@ struct AssetBundle {
     assets: Vec<Asset>,
 }
 
+#[derive(Debug, Clone)]
+struct Asset {
+    path: String,
+    offset: i64,
+    size: i64,
+    flags: u32,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct StorageBlock {
+    uncompressed_size: u32,
+    compressed_size: u32,
+    flags: u16,
+}
+
 #[derive(Debug, Copy, Clone)]
 enum Signature {
     Raw,
@@ -95,8 +112,8 @@ impl AssetBundle {
         };
 
         match signature {
-            Signature::FS => Self::load_raw(reader, header),
-            Signature::Raw | Signature::Web => Self::load_unityfs(reader, header),
+            Signature::FS => Self::load_unityfs(reader, header),
+            Signature::Raw | Signature::Web => Self::load_raw(reader, header),
         }
     }
 
@@ -165,24 +182,134 @@ impl AssetBundle {
 
     fn load_unityfs<R: Read + Seek>(
         mut reader: R,
-        header: AssetBundleHeader,
+        header: PartialAssetBundleHeader,
     ) -> Result<Self, Error> {
         let file_size = reader.read_i64::<BigEndian>()? as usize;
         let ciblock_size = reader.read_u32::<BigEndian>()? as usize;
         let uiblock_size = reader.read_u32::<BigEndian>()? as usize;
         let flags = reader.read_u32::<BigEndian>()?;
-        let compression = (flags & 0x3F).try_into()?;
+        let compression: CompressionType = (flags & 0x3F).try_into()?;
         let eof_metadata = flags & 0x80;
+
         let mut orig_pos = None;
         if eof_metadata != 0 {
-            orig_pos = Some(reader.seek(SeekFrom::Current(0))?) as u64;
-            reader.seek(SeekFrom::End(-ciblock_size as i64))?;
+            orig_pos = Some(reader.seek(SeekFrom::Current(0))?);
+            reader.seek(SeekFrom::End(-(ciblock_size as i64)))?;
         }
-        // TODO
-        if eof_metadata != 0 {
-            reader.seek(SeekFrom::Start(orig_pos.unwrap()))?;
+        let mut ciblocks_info = vec![0; ciblock_size];
+        reader.read_exact(&mut ciblocks_info)?;
+        if let Some(orig_pos) = orig_pos {
+            reader.seek(SeekFrom::Start(orig_pos))?;
         }
+        let blocks_info = compression.decompress(&ciblocks_info, uiblock_size)?;
+
+        let mut blocks_info = Cursor::new(blocks_info);
+        let mut hash = [0; 16];
+        blocks_info.read_exact(&mut hash)?;
+        let block_count = blocks_info.read_u32::<BigEndian>()? as usize;
+        let mut storage_blocks = Vec::with_capacity(block_count);
+        for _ in 0..block_count {
+            storage_blocks.push(StorageBlock {
+                uncompressed_size: blocks_info.read_u32::<BigEndian>()?,
+                compressed_size: blocks_info.read_u32::<BigEndian>()?,
+                flags: blocks_info.read_u16::<BigEndian>()?,
+            });
+        }
+
+        let node_count = blocks_info.read_u32::<BigEndian>()? as usize;
+        let mut nodes = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            let offset = blocks_info.read_i64::<BigEndian>()?;
+            let size = blocks_info.read_i64::<BigEndian>()?;
+            let node_flags = blocks_info.read_u32::<BigEndian>()?;
+            let mut path = Vec::new();
+            blocks_info.read_until(0, &mut path)?;
+            path.pop(); // `read_until` includes the trailing NUL delimiter
+            let path = String::from_utf8(path)?;
+            nodes.push((offset, size, node_flags, path));
+        }
+
+        let mut data = Vec::with_capacity(
+            storage_blocks
+                .iter()
+                .map(|block| block.uncompressed_size as usize)
+                .sum(),
+        );
+        for block in &storage_blocks {
+            let block_compression: CompressionType = (u32::from(block.flags) & 0x3F).try_into()?;
+            let mut compressed = vec![0; block.compressed_size as usize];
+            reader.read_exact(&mut compressed)?;
+            let uncompressed =
+                block_compression.decompress(&compressed, block.uncompressed_size as usize)?;
+            data.extend_from_slice(&uncompressed);
+        }
+
+        let assets = nodes
+            .into_iter()
+            .map(|(offset, size, flags, path)| Asset {
+                data: data[offset as usize..(offset + size) as usize].to_vec(),
+                path,
+                offset,
+                size,
+                flags,
+            })
+            .collect();
+
+        let header = AssetBundleHeader::FS {
+            signature: header.signature,
+            format_version: header.format_version,
+            unity_version: header.unity_version,
+            generator_version: header.generator_version,
+            file_size,
+            ciblock_size,
+            uiblock_size,
+        };
+
+        Ok(Self { header, assets })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial_header() -> PartialAssetBundleHeader {
+        PartialAssetBundleHeader {
+            signature: Signature::FS,
+            format_version: 6,
+            unity_version: "5.x.x".to_string(),
+            generator_version: "5.x.x".to_string(),
+        }
+    }
+
+    #[test]
+    fn load_unityfs_decompresses_blocks_and_slices_assets() {
+        let payload = b"hello asset bytes";
+
+        let mut blocks_info = Vec::new();
+        blocks_info.extend_from_slice(&[0; 16]); // hash
+        blocks_info.extend_from_slice(&1u32.to_be_bytes()); // block count
+        blocks_info.extend_from_slice(&(payload.len() as u32).to_be_bytes()); // uncompressed size
+        blocks_info.extend_from_slice(&(payload.len() as u32).to_be_bytes()); // compressed size
+        blocks_info.extend_from_slice(&0u16.to_be_bytes()); // flags: compression None
+        blocks_info.extend_from_slice(&1u32.to_be_bytes()); // node count
+        blocks_info.extend_from_slice(&0i64.to_be_bytes()); // offset
+        blocks_info.extend_from_slice(&(payload.len() as i64).to_be_bytes()); // size
+        blocks_info.extend_from_slice(&0u32.to_be_bytes()); // flags
+        blocks_info.extend_from_slice(b"asset.bin\0");
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&0i64.to_be_bytes()); // file_size
+        body.extend_from_slice(&(blocks_info.len() as u32).to_be_bytes()); // ciblock_size
+        body.extend_from_slice(&(blocks_info.len() as u32).to_be_bytes()); // uiblock_size
+        body.extend_from_slice(&0u32.to_be_bytes()); // flags: compression None, blocks-info in place
+        body.extend_from_slice(&blocks_info);
+        body.extend_from_slice(payload);
+
+        let bundle = AssetBundle::load_unityfs(Cursor::new(body), partial_header()).unwrap();
 
-        Ok(Self)
+        assert_eq!(bundle.assets.len(), 1);
+        assert_eq!(bundle.assets[0].path, "asset.bin");
+        assert_eq!(bundle.assets[0].data, payload);
     }
 }
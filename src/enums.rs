@@ -1,7 +1,8 @@
 use crate::Error;
 use std::convert::TryFrom;
 
-enum CompressionType {
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum CompressionType {
     None,
     LZMA,
     LZ4,
@@ -23,3 +24,35 @@ impl TryFrom<u32> for CompressionType {
         }
     }
 }
+
+impl CompressionType {
+    /// Decompresses a single block of `data` that is known to expand to `uncompressed_size`
+    /// bytes once decoded.
+    pub(crate) fn decompress(self, data: &[u8], uncompressed_size: usize) -> Result<Vec<u8>, Error> {
+        match self {
+            CompressionType::None => Ok(data.to_vec()),
+            CompressionType::LZMA => {
+                // `xz2` has no raw-filter-chain decoder, only the legacy `.lzma` stream
+                // decoder (`new_lzma_decoder`), which expects its own 13-byte header: the
+                // 5 props bytes UnityFS already stores, followed by an 8-byte
+                // little-endian uncompressed size. Synthesize that header in front of the
+                // payload so the legacy decoder can be reused instead.
+                let (props, payload) = data.split_at(5);
+                let mut stream_data = Vec::with_capacity(13 + payload.len());
+                stream_data.extend_from_slice(props);
+                stream_data.extend_from_slice(&(uncompressed_size as u64).to_le_bytes());
+                stream_data.extend_from_slice(payload);
+
+                let stream = xz2::stream::Stream::new_lzma_decoder(u64::MAX)?;
+                let mut decoder = xz2::read::XzDecoder::new_stream(stream_data.as_slice(), stream);
+                let mut out = Vec::with_capacity(uncompressed_size);
+                std::io::Read::read_to_end(&mut decoder, &mut out)?;
+                Ok(out)
+            }
+            CompressionType::LZ4 | CompressionType::LZ4HC => {
+                Ok(lz4::block::decompress(data, Some(uncompressed_size as i32))?)
+            }
+            CompressionType::LZ4AM => Err(Error::CompressionType(4)),
+        }
+    }
+}
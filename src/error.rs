@@ -9,9 +9,15 @@ pub enum Error {
     #[error("Unrecognized file signature {0:?}")]
     Signature(Vec<u8>),
 
+    #[error("Unrecognized compression type `{0}`")]
+    CompressionType(u32),
+
     #[error("IO error")]
     IO(#[from] io::Error),
 
     #[error("Invalid UTF-8")]
     UTF8(#[from] FromUtf8Error),
+
+    #[error("LZMA error")]
+    Lzma(#[from] xz2::stream::Error),
 }